@@ -0,0 +1,70 @@
+extern crate cargo;
+extern crate rustc_serialize;
+
+use std::env;
+
+use cargo::ops::{self, FixOptions};
+use cargo::util::{CliResult, Config, MessageFormat};
+use cargo::core::{Verbosity, ColorConfig};
+
+#[derive(RustcDecodable)]
+struct Flags {
+    flag_manifest_path: Option<String>,
+    flag_verbose: u32,
+    flag_quiet: Option<bool>,
+    flag_message_format: Option<String>,
+}
+
+pub const USAGE: &'static str = "
+Automatically apply rustc's machine-applicable suggestions
+
+Usage:
+    cargo fix [options]
+
+Options:
+    -h, --help               Print this message
+    --manifest-path PATH     Path to the manifest to fix
+    --message-format FMT     Error format to use [default: human]
+    -v, --verbose ...        Use verbose output
+    -q, --quiet              No output printed to stdout
+";
+
+fn main() {
+    let args: Vec<_> = env::args().collect();
+    let shell = cargo::shell(Verbosity::Normal, ColorConfig::Auto, MessageFormat::Human);
+    let cwd = env::current_dir().expect("could not determine cwd");
+    let config = Config::new(shell, cwd);
+
+    let result = cargo::call_main_without_stdin(execute, &config, USAGE, &args, false);
+
+    match result {
+        Ok(()) => {}
+        Err(e) => cargo::exit_with_error(e, &mut config.shell()),
+    }
+}
+
+fn execute(flags: Flags, config: &Config) -> CliResult {
+    let verbosity = if flags.flag_quiet == Some(true) {
+        Verbosity::Quiet
+    } else if flags.flag_verbose > 0 {
+        Verbosity::Verbose
+    } else {
+        Verbosity::Normal
+    };
+    let message_format = match flags.flag_message_format.as_ref().map(|s| s.as_str()) {
+        Some("json") => MessageFormat::Json,
+        _ => MessageFormat::Human,
+    };
+    config.configure_shell(verbosity, ColorConfig::Auto, message_format)?;
+
+    let manifest_path = flags.flag_manifest_path
+        .map(|p| config.cwd().join(p))
+        .unwrap_or_else(|| config.cwd().join("Cargo.toml"));
+
+    let opts = FixOptions { manifest_path: &manifest_path };
+
+    let ws_root = manifest_path.parent().unwrap_or(config.cwd());
+    ops::fix(ws_root, config, &opts)?;
+
+    Ok(())
+}
@@ -0,0 +1,3 @@
+pub use self::shell::{Shell, MultiShell, ShellConfig, Verbosity, ColorConfig};
+
+pub mod shell;
@@ -0,0 +1,121 @@
+use std::io::prelude::*;
+
+use term::color::Color;
+
+use util::{CargoResult, MessageFormat};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Verbosity {
+    Verbose,
+    Normal,
+    Quiet,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ColorConfig {
+    Auto,
+    Always,
+    Never,
+}
+
+impl Default for ColorConfig {
+    fn default() -> ColorConfig { ColorConfig::Auto }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct ShellConfig {
+    pub color_config: ColorConfig,
+    pub tty: bool,
+}
+
+pub struct Shell {
+    err: Box<Write + Send>,
+    config: ShellConfig,
+}
+
+impl Shell {
+    pub fn create<T: FnMut() -> Box<Write + Send>>(mut out_fn: T, config: ShellConfig) -> Shell {
+        Shell { err: out_fn(), config: config }
+    }
+
+    pub fn say<T: ToString>(&mut self, message: T, _color: Color) -> CargoResult<()> {
+        writeln!(self.err, "{}", message.to_string())?;
+        Ok(())
+    }
+
+    pub fn error<T: ToString>(&mut self, message: T) -> CargoResult<()> {
+        writeln!(self.err, "error: {}", message.to_string())?;
+        Ok(())
+    }
+}
+
+#[derive(Serialize)]
+struct StatusMessage {
+    reason: &'static str,
+    message: String,
+}
+
+pub struct MultiShell {
+    out: Shell,
+    err: Shell,
+    verbosity: Verbosity,
+    message_format: MessageFormat,
+}
+
+impl MultiShell {
+    pub fn new(out: Shell, err: Shell, verbosity: Verbosity) -> MultiShell {
+        MultiShell {
+            out: out,
+            err: err,
+            verbosity: verbosity,
+            message_format: MessageFormat::Human,
+        }
+    }
+
+    pub fn out(&mut self) -> &mut Shell {
+        &mut self.out
+    }
+
+    pub fn err(&mut self) -> &mut Shell {
+        &mut self.err
+    }
+
+    pub fn say<T: ToString>(&mut self, message: T, color: Color) -> CargoResult<()> {
+        if self.verbosity == Verbosity::Quiet {
+            return Ok(());
+        }
+        match self.message_format {
+            MessageFormat::Human => self.out.say(message, color),
+            MessageFormat::Json => {
+                ::print_json(&StatusMessage { reason: "message", message: message.to_string() });
+                Ok(())
+            }
+        }
+    }
+
+    pub fn error<T: ToString>(&mut self, message: T) -> CargoResult<()> {
+        match self.message_format {
+            MessageFormat::Human => self.err.error(message),
+            MessageFormat::Json => {
+                ::print_json(&StatusMessage { reason: "error", message: message.to_string() });
+                Ok(())
+            }
+        }
+    }
+
+    pub fn get_verbose(&self) -> Verbosity {
+        self.verbosity
+    }
+
+    pub fn set_verbosity(&mut self, verbosity: Verbosity) {
+        self.verbosity = verbosity;
+    }
+
+    pub fn message_format(&self) -> MessageFormat {
+        self.message_format
+    }
+
+    pub fn set_message_format(&mut self, message_format: MessageFormat) {
+        self.message_format = message_format;
+    }
+}
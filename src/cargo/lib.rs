@@ -44,7 +44,7 @@ use core::{Shell, MultiShell, ShellConfig, Verbosity, ColorConfig};
 use core::shell::Verbosity::{Verbose};
 use term::color::{BLACK};
 
-pub use util::{CargoError, CargoErrorKind, CargoResult, CliError, CliResult, Config};
+pub use util::{CargoError, CargoErrorKind, CargoResult, CliError, CliResult, Config, MessageFormat};
 
 pub const CARGO_ENV: &'static str = "CARGO";
 
@@ -129,23 +129,30 @@ pub fn print_json<T: ser::Serialize>(obj: &T) {
     println!("{}", encoded);
 }
 
-pub fn shell(verbosity: Verbosity, color_config: ColorConfig) -> MultiShell {
+pub fn shell(verbosity: Verbosity, color_config: ColorConfig, message_format: MessageFormat) -> MultiShell {
     enum Output {
         Stdout,
         Stderr,
     }
 
-    let tty = isatty(Output::Stderr);
+    // In JSON mode cargo's own status messages go out as JSON objects, so
+    // there's no point probing stdout/stderr for a terminal to decide on
+    // colors.
+    let json = message_format == MessageFormat::Json;
+
+    let tty = !json && isatty(Output::Stderr);
 
     let config = ShellConfig { color_config: color_config, tty: tty };
     let err = Shell::create(|| Box::new(io::stderr()), config);
 
-    let tty = isatty(Output::Stdout);
+    let tty = !json && isatty(Output::Stdout);
 
     let config = ShellConfig { color_config: color_config, tty: tty };
     let out = Shell::create(|| Box::new(io::stdout()), config);
 
-    return MultiShell::new(out, err, verbosity);
+    let mut shell = MultiShell::new(out, err, verbosity);
+    shell.set_message_format(message_format);
+    return shell;
 
     #[cfg(unix)]
     fn isatty(output: Output) -> bool {
@@ -184,17 +191,30 @@ pub fn exit_with_error(err: CliError, shell: &mut MultiShell) -> ! {
     let hide = unknown && shell.get_verbose() != Verbose;
 
     if let Some(error) = error {
-        let _ignored_result = if hide {
-            shell.error("An unknown error occurred")
-        } else if fatal {
-            shell.error(&error)
+        if shell.message_format() == MessageFormat::Json {
+            if hide {
+                print_json(&json!({
+                    "reason": "error",
+                    "message": "An unknown error occurred",
+                }));
+            } else if fatal {
+                print_error_json(error);
+            } else {
+                print_json(&json!({ "reason": "message", "message": error.to_string() }));
+            }
         } else {
-            shell.say(&error, BLACK)
-        };
-
-        if !handle_cause(error, shell) || hide {
-            let _ = shell.err().say("\nTo learn more, run the command again \
-                                     with --verbose.".to_string(), BLACK);
+            let _ignored_result = if hide {
+                shell.error("An unknown error occurred")
+            } else if fatal {
+                shell.error(&error)
+            } else {
+                shell.say(&error, BLACK)
+            };
+
+            if !handle_cause(error, shell) || hide {
+                let _ = shell.err().say("\nTo learn more, run the command again \
+                                         with --verbose.".to_string(), BLACK);
+            }
         }
     }
 
@@ -204,8 +224,30 @@ pub fn exit_with_error(err: CliError, shell: &mut MultiShell) -> ! {
 pub fn handle_error(err: CargoError, shell: &mut MultiShell) {
     debug!("handle_error; err={:?}", &err);
 
-    let _ignored_result = shell.error(&err);
-    handle_cause(err, shell);
+    if shell.message_format() == MessageFormat::Json {
+        print_error_json(err);
+    } else {
+        let _ignored_result = shell.error(&err);
+        handle_cause(err, shell);
+    }
+}
+
+#[derive(Serialize)]
+struct ErrorMessage {
+    reason: &'static str,
+    message: String,
+    caused_by: Vec<String>,
+}
+
+// Serializes the full `Caused by` chain as a single JSON object instead of
+// the colored, line-oriented text `handle_cause` writes for humans, so
+// tooling can consume the whole context without screen-scraping.
+fn print_error_json<E, EKind>(cargo_err: E)
+    where E: ChainedError<ErrorKind=EKind> + 'static
+{
+    let caused_by = cargo_err.iter().skip(1).map(|e| e.to_string()).collect();
+    let message = cargo_err.to_string();
+    print_json(&ErrorMessage { reason: "error", message: message, caused_by: caused_by });
 }
 
 fn handle_cause<E, EKind>(cargo_err: E, shell: &mut MultiShell) -> bool
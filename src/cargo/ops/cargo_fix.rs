@@ -0,0 +1,426 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use serde_json;
+use term::color::{GREEN, YELLOW};
+
+use util::{process, BackupSet, CargoResult, CargoResultExt, Config, MessageFormat};
+
+/// Diagnostics are re-requested and re-applied until either no new
+/// machine-applicable suggestions appear or this many rounds have run,
+/// to avoid oscillating between two suggestions that keep re-triggering
+/// each other.
+const MAX_FIX_ITERATIONS: u32 = 4;
+
+pub struct FixOptions<'a> {
+    pub manifest_path: &'a Path,
+}
+
+// `cargo build --message-format=json` wraps every rustc diagnostic in an
+// envelope like `{"reason":"compiler-message","message":{...}}`; the actual
+// diagnostic (with its `level`, `spans` and `children`) lives under
+// `message`, not at the top level.
+#[derive(Deserialize)]
+struct CargoMessage {
+    reason: String,
+    #[serde(default)]
+    message: Option<Diagnostic>,
+}
+
+#[derive(Deserialize)]
+struct Diagnostic {
+    #[serde(default)]
+    level: String,
+    #[serde(default)]
+    spans: Vec<DiagnosticSpan>,
+    #[serde(default)]
+    children: Vec<Diagnostic>,
+}
+
+#[derive(Deserialize, Clone, PartialEq)]
+enum Applicability {
+    MachineApplicable,
+    HasPlaceholders,
+    MaybeIncorrect,
+    Unspecified,
+}
+
+#[derive(Deserialize, Clone)]
+struct DiagnosticSpan {
+    file_name: String,
+    byte_start: usize,
+    byte_end: usize,
+    suggested_replacement: Option<String>,
+    suggestion_applicability: Option<Applicability>,
+}
+
+#[derive(Clone)]
+struct Edit {
+    byte_start: usize,
+    byte_end: usize,
+    replacement: String,
+}
+
+// Why the loop in `fix()` broke out, so the "reached the iteration limit"
+// message is only printed when the cap was genuinely hit rather than on
+// every other kind of early exit.
+enum LoopExit {
+    NoMoreSuggestions,
+    AllEditsStale,
+    Reverted,
+    IterationLimitReached,
+}
+
+pub fn fix(ws_root: &Path, config: &Config, opts: &FixOptions) -> CargoResult<()> {
+    let mut exit = LoopExit::IterationLimitReached;
+
+    for _ in 0..MAX_FIX_ITERATIONS {
+        // `backups` is filled in as each diagnostic's spans are parsed, before
+        // `edits_by_file` is computed and applied, so `verify_unchanged` can
+        // catch a file that changes between then and the write. The build's
+        // output is only read once the build has finished, so an edit made
+        // while the build is still running is recorded as the "original".
+        let mut backups = BackupSet::new()?;
+        let diagnostics = run_build_collecting_diagnostics(ws_root, config, opts, &mut backups)?;
+        let edits_by_file = collect_machine_applicable_edits(ws_root, &diagnostics)?;
+
+        if edits_by_file.is_empty() {
+            exit = LoopExit::NoMoreSuggestions;
+            break;
+        }
+
+        let error_count_before = count_errors(&diagnostics);
+
+        let (applied, skipped) = apply_edits(&edits_by_file, &backups)?;
+        for (file, count) in &applied {
+            config.shell().say(
+                format!("Fixed {} suggestion(s) in `{}`", count, file.display()),
+                GREEN,
+            )?;
+        }
+        for file in &skipped {
+            config.shell().say(
+                format!("Skipping `{}`: it changed on disk since the diagnostics \
+                         were collected", file.display()),
+                YELLOW,
+            )?;
+        }
+
+        if applied.is_empty() {
+            exit = LoopExit::AllEditsStale;
+            break;
+        }
+
+        let mut verify_backups = BackupSet::new()?;
+        let diagnostics_after =
+            run_build_collecting_diagnostics(ws_root, config, opts, &mut verify_backups)?;
+        let error_count_after = count_errors(&diagnostics_after);
+        if error_count_after > error_count_before {
+            backups.restore_all(
+                &mut config.shell(),
+                "the fix made the build worse, reverting",
+            )?;
+            exit = LoopExit::Reverted;
+            break;
+        }
+    }
+
+    if let LoopExit::IterationLimitReached = exit {
+        config.shell().say(
+            format!("reached the {}-iteration fix limit; some suggestions may remain", MAX_FIX_ITERATIONS),
+            GREEN,
+        )?;
+    }
+
+    Ok(())
+}
+
+fn run_build_collecting_diagnostics(
+    ws_root: &Path,
+    config: &Config,
+    opts: &FixOptions,
+    backups: &mut BackupSet,
+) -> CargoResult<Vec<Diagnostic>> {
+    let output = process("cargo")
+        .arg("build")
+        .arg("--manifest-path")
+        .arg(opts.manifest_path)
+        .arg("--message-format")
+        .arg("json")
+        .cwd(ws_root)
+        .exec_with_output()
+        .chain_err(|| "failed to run rustc to collect diagnostics")?;
+
+    // `cargo fix`'s own inner build always requests JSON from rustc, since
+    // it has to parse suggestions regardless of the user-facing message
+    // format. When the user asked for `--message-format=json` themselves,
+    // also forward each `compiler-message` line of the inner build (cargo's
+    // envelope around the rustc diagnostic) verbatim to stdout. Wiring `MessageFormat` into an arbitrary `cargo
+    // build` isn't possible from here, since that lives in `ops::compile`,
+    // which doesn't exist in this tree; this only covers the one build
+    // `cargo fix` itself drives.
+    let forward_raw = config.message_format() == MessageFormat::Json;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut diagnostics = Vec::new();
+    for line in stdout.lines() {
+        let msg = match serde_json::from_str::<CargoMessage>(line) {
+            Ok(msg) => msg,
+            Err(_) => continue,
+        };
+        if msg.reason != "compiler-message" {
+            continue;
+        }
+        if forward_raw {
+            println!("{}", line);
+        }
+        if let Some(diag) = msg.message {
+            // Snapshot each file as soon as its diagnostic is parsed, before
+            // overlap resolution, dedup or the second verification build run.
+            snapshot_diagnostic_files(ws_root, &diag, backups)?;
+            diagnostics.push(diag);
+        }
+    }
+    Ok(diagnostics)
+}
+
+// Walks a diagnostic (and its children) backing up every file it names with
+// a machine-applicable suggestion, mirroring the filter `collect_from_diagnostic`
+// applies later when it actually builds the edit list.
+fn snapshot_diagnostic_files(
+    ws_root: &Path,
+    diag: &Diagnostic,
+    backups: &mut BackupSet,
+) -> CargoResult<()> {
+    let canonical_ws_root = ws_root.canonicalize()
+        .chain_err(|| format!("failed to canonicalize workspace root `{}`", ws_root.display()))?;
+
+    for span in &diag.spans {
+        if span.suggestion_applicability != Some(Applicability::MachineApplicable) {
+            continue;
+        }
+        if span.suggested_replacement.is_none() {
+            continue;
+        }
+        let file = match ws_root.join(&span.file_name).canonicalize() {
+            Ok(p) => p,
+            Err(_) => continue,
+        };
+        if !file.starts_with(&canonical_ws_root) {
+            continue;
+        }
+        backups.snapshot(&file)?;
+    }
+
+    for child in &diag.children {
+        snapshot_diagnostic_files(ws_root, child, backups)?;
+    }
+
+    Ok(())
+}
+
+// Only `level == "error"` counts toward the before/after comparison;
+// `diagnostics` also includes warnings, notes and other non-error levels
+// that shift from unrelated causes and shouldn't trigger a revert.
+fn count_errors(diagnostics: &[Diagnostic]) -> usize {
+    diagnostics.iter().filter(|d| d.level == "error").count()
+}
+
+fn collect_machine_applicable_edits(
+    ws_root: &Path,
+    diagnostics: &[Diagnostic],
+) -> CargoResult<HashMap<PathBuf, Vec<Edit>>> {
+    // Resolve symlinks and `..` components once so the later containment
+    // check can't be fooled by a `file_name` like `../../etc/passwd`, which
+    // would otherwise lexically "start with" `ws_root` without actually
+    // living inside it.
+    let canonical_ws_root = ws_root.canonicalize()
+        .chain_err(|| format!("failed to canonicalize workspace root `{}`", ws_root.display()))?;
+
+    let mut by_file: HashMap<PathBuf, Vec<Edit>> = HashMap::new();
+    let mut seen = HashSet::new();
+
+    for diag in diagnostics {
+        collect_from_diagnostic(ws_root, &canonical_ws_root, diag, &mut by_file, &mut seen);
+    }
+
+    // Sort by start offset and drop any suggestion whose span overlaps one
+    // already accepted; overlapping edits cannot both be applied.
+    for edits in by_file.values_mut() {
+        edits.sort_by_key(|e| e.byte_start);
+        let mut accepted: Vec<Edit> = Vec::new();
+        for edit in edits.drain(..) {
+            let overlaps = accepted
+                .iter()
+                .any(|a| edit.byte_start < a.byte_end && a.byte_start < edit.byte_end);
+            if !overlaps {
+                accepted.push(edit);
+            }
+        }
+        *edits = accepted;
+    }
+
+    Ok(by_file)
+}
+
+fn collect_from_diagnostic(
+    ws_root: &Path,
+    canonical_ws_root: &Path,
+    diag: &Diagnostic,
+    by_file: &mut HashMap<PathBuf, Vec<Edit>>,
+    seen: &mut HashSet<(PathBuf, usize, usize, String)>,
+) {
+    for span in &diag.spans {
+        if span.suggestion_applicability != Some(Applicability::MachineApplicable) {
+            continue;
+        }
+        let replacement = match span.suggested_replacement {
+            Some(ref r) => r.clone(),
+            None => continue,
+        };
+        let file = ws_root.join(&span.file_name);
+        let canonical_file = match file.canonicalize() {
+            Ok(p) => p,
+            Err(_) => continue,
+        };
+        if !canonical_file.starts_with(canonical_ws_root) {
+            continue;
+        }
+
+        let key = (canonical_file.clone(), span.byte_start, span.byte_end, replacement.clone());
+        if !seen.insert(key) {
+            continue;
+        }
+
+        by_file.entry(canonical_file).or_insert_with(Vec::new).push(Edit {
+            byte_start: span.byte_start,
+            byte_end: span.byte_end,
+            replacement: replacement,
+        });
+    }
+
+    for child in &diag.children {
+        collect_from_diagnostic(ws_root, canonical_ws_root, child, by_file, seen);
+    }
+}
+
+fn apply_edits(
+    edits_by_file: &HashMap<PathBuf, Vec<Edit>>,
+    backups: &BackupSet,
+) -> CargoResult<(HashMap<PathBuf, usize>, Vec<PathBuf>)> {
+    let mut applied = HashMap::new();
+    let mut skipped = Vec::new();
+
+    for (file, edits) in edits_by_file {
+        if !backups.verify_unchanged(file)? {
+            skipped.push(file.clone());
+            continue;
+        }
+
+        let mut contents = String::new();
+        File::open(file)
+            .chain_err(|| format!("failed to open `{}` for reading", file.display()))?
+            .read_to_string(&mut contents)
+            .chain_err(|| format!("failed to read `{}`", file.display()))?;
+
+        let mut bytes = contents.into_bytes();
+        // Walk from the highest offset down so earlier offsets stay valid
+        // as later-in-file edits shift the bytes around them.
+        for edit in edits.iter().rev() {
+            bytes.splice(edit.byte_start..edit.byte_end, edit.replacement.bytes());
+        }
+
+        File::create(file)
+            .chain_err(|| format!("failed to open `{}` for writing", file.display()))?
+            .write_all(&bytes)
+            .chain_err(|| format!("failed to write `{}`", file.display()))?;
+
+        applied.insert(file.clone(), edits.len());
+    }
+
+    Ok((applied, skipped))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+
+    use tempdir::TempDir;
+
+    fn span(start: usize, end: usize, replacement: &str, file_name: &str) -> DiagnosticSpan {
+        DiagnosticSpan {
+            file_name: file_name.to_string(),
+            byte_start: start,
+            byte_end: end,
+            suggested_replacement: Some(replacement.to_string()),
+            suggestion_applicability: Some(Applicability::MachineApplicable),
+        }
+    }
+
+    fn diagnostic(spans: Vec<DiagnosticSpan>) -> Diagnostic {
+        Diagnostic { level: "warning".to_string(), spans: spans, children: Vec::new() }
+    }
+
+    #[test]
+    fn overlapping_suggestions_keep_only_the_first() {
+        let dir = TempDir::new("cargo-fix-test").unwrap();
+        let file = dir.path().join("lib.rs");
+        File::create(&file).unwrap().write_all(b"0123456789").unwrap();
+
+        let diagnostics = vec![diagnostic(vec![
+            span(0, 4, "aaaa", "lib.rs"),
+            span(2, 6, "bbbb", "lib.rs"),
+        ])];
+
+        let edits = collect_machine_applicable_edits(dir.path(), &diagnostics).unwrap();
+        let file_edits = &edits[&file.canonicalize().unwrap()];
+
+        assert_eq!(file_edits.len(), 1);
+        assert_eq!(file_edits[0].replacement, "aaaa");
+    }
+
+    #[test]
+    fn identical_suggestions_are_deduplicated() {
+        let dir = TempDir::new("cargo-fix-test").unwrap();
+        let file = dir.path().join("lib.rs");
+        File::create(&file).unwrap().write_all(b"0123456789").unwrap();
+
+        let diagnostics = vec![
+            diagnostic(vec![span(0, 4, "aaaa", "lib.rs")]),
+            diagnostic(vec![span(0, 4, "aaaa", "lib.rs")]),
+        ];
+
+        let edits = collect_machine_applicable_edits(dir.path(), &diagnostics).unwrap();
+        let file_edits = &edits[&file.canonicalize().unwrap()];
+
+        assert_eq!(file_edits.len(), 1);
+    }
+
+    #[test]
+    fn apply_edits_applies_sorted_non_overlapping_suggestions() {
+        let dir = TempDir::new("cargo-fix-test").unwrap();
+        let file = dir.path().join("lib.rs");
+        File::create(&file).unwrap().write_all(b"0123456789").unwrap();
+        let canonical_file = file.canonicalize().unwrap();
+
+        let mut edits_by_file = HashMap::new();
+        edits_by_file.insert(canonical_file.clone(), vec![
+            Edit { byte_start: 0, byte_end: 1, replacement: "X".to_string() },
+            Edit { byte_start: 8, byte_end: 9, replacement: "Y".to_string() },
+        ]);
+
+        let mut backups = BackupSet::new().unwrap();
+        backups.snapshot(&canonical_file).unwrap();
+
+        let (applied, skipped) = apply_edits(&edits_by_file, &backups).unwrap();
+        assert_eq!(applied[&canonical_file], 2);
+        assert!(skipped.is_empty());
+
+        let mut contents = String::new();
+        File::open(&canonical_file).unwrap().read_to_string(&mut contents).unwrap();
+        assert_eq!(contents, "X1234567Y9");
+    }
+}
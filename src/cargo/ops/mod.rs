@@ -0,0 +1,3 @@
+pub use self::cargo_fix::{fix, FixOptions};
+
+mod cargo_fix;
@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::fs::{self, File};
+use std::hash::{Hash, Hasher};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use filetime::{self, FileTime};
+use tempdir::TempDir;
+
+use core::MultiShell;
+use term::color::YELLOW;
+use util::{CargoResult, CargoResultExt};
+
+struct BackupEntry {
+    backup_path: PathBuf,
+    original_hash: u64,
+    mtime: FileTime,
+}
+
+/// Snapshots files before they're rewritten in place so a failed
+/// verification build can roll every one of them back, and so a file that
+/// changed on disk after it was snapshotted can be detected and skipped
+/// rather than silently clobbered. `restore_all` restores the files one at a
+/// time: the rollback is not atomic, and if one restore fails the files not
+/// yet restored are left as they are.
+pub struct BackupSet {
+    dir: TempDir,
+    entries: HashMap<PathBuf, BackupEntry>,
+}
+
+fn hash_file(path: &Path) -> CargoResult<u64> {
+    let mut contents = Vec::new();
+    File::open(path)
+        .and_then(|mut f| f.read_to_end(&mut contents))
+        .chain_err(|| format!("failed to read `{}` for backup", path.display()))?;
+    let mut hasher = DefaultHasher::new();
+    contents.hash(&mut hasher);
+    Ok(hasher.finish())
+}
+
+impl BackupSet {
+    pub fn new() -> CargoResult<BackupSet> {
+        let dir = TempDir::new("cargo-fix-backup")
+            .chain_err(|| "failed to create a temporary directory for backups")?;
+        Ok(BackupSet { dir: dir, entries: HashMap::new() })
+    }
+
+    /// Copies `file`'s current bytes and mtime into the backup area and
+    /// records its hash, so `verify_unchanged` can later tell whether
+    /// something else modified the file before we got to writing it.
+    pub fn snapshot(&mut self, file: &Path) -> CargoResult<()> {
+        if self.entries.contains_key(file) {
+            return Ok(());
+        }
+
+        let hash = hash_file(file)?;
+        let metadata = fs::metadata(file)
+            .chain_err(|| format!("failed to stat `{}`", file.display()))?;
+        let mtime = FileTime::from_last_modification_time(&metadata);
+
+        let backup_path = self.dir.path().join(self.entries.len().to_string());
+        fs::copy(file, &backup_path)
+            .chain_err(|| format!("failed to back up `{}`", file.display()))?;
+        filetime::set_file_times(&backup_path, mtime, mtime)
+            .chain_err(|| format!("failed to preserve mtime for `{}`", file.display()))?;
+
+        self.entries.insert(file.to_path_buf(), BackupEntry {
+            backup_path: backup_path,
+            original_hash: hash,
+            mtime: mtime,
+        });
+        Ok(())
+    }
+
+    /// Returns `true` if `file` still has the same contents it had when it
+    /// was snapshotted. A `false` result means something edited the file
+    /// out from under us (e.g. a concurrent edit, or a stale diagnostic
+    /// referring to a file that has since changed) and it must not be
+    /// touched.
+    pub fn verify_unchanged(&self, file: &Path) -> CargoResult<bool> {
+        match self.entries.get(file) {
+            Some(entry) => Ok(hash_file(file)? == entry.original_hash),
+            None => Ok(true),
+        }
+    }
+
+    /// Restores every snapshotted file to its original bytes and mtime and
+    /// reports through `shell` which files were reverted and why.
+    pub fn restore_all(&self, shell: &mut MultiShell, reason: &str) -> CargoResult<()> {
+        for (file, entry) in &self.entries {
+            fs::copy(&entry.backup_path, file)
+                .chain_err(|| format!("failed to restore `{}`", file.display()))?;
+            filetime::set_file_times(file, entry.mtime, entry.mtime)
+                .chain_err(|| format!("failed to restore mtime for `{}`", file.display()))?;
+            shell.say(
+                format!("Reverted `{}`: {}", file.display(), reason),
+                YELLOW,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::io::{Read, Write};
+
+    use core::{ColorConfig, Shell, ShellConfig, Verbosity};
+
+    fn test_shell() -> MultiShell {
+        let config = ShellConfig { color_config: ColorConfig::Never, tty: false };
+        let out = Shell::create(|| Box::new(Vec::new()) as Box<::std::io::Write + Send>, config);
+        let err = Shell::create(|| Box::new(Vec::new()) as Box<::std::io::Write + Send>, config);
+        MultiShell::new(out, err, Verbosity::Quiet)
+    }
+
+    #[test]
+    fn verify_unchanged_detects_a_concurrent_edit() {
+        let dir = TempDir::new("cargo-fix-backup-test").unwrap();
+        let file = dir.path().join("lib.rs");
+        File::create(&file).unwrap().write_all(b"original").unwrap();
+
+        let mut backups = BackupSet::new().unwrap();
+        backups.snapshot(&file).unwrap();
+        assert!(backups.verify_unchanged(&file).unwrap());
+
+        File::create(&file).unwrap().write_all(b"changed").unwrap();
+        assert!(!backups.verify_unchanged(&file).unwrap());
+    }
+
+    #[test]
+    fn restore_all_puts_back_the_original_bytes() {
+        let dir = TempDir::new("cargo-fix-backup-test").unwrap();
+        let file = dir.path().join("lib.rs");
+        File::create(&file).unwrap().write_all(b"original").unwrap();
+
+        let mut backups = BackupSet::new().unwrap();
+        backups.snapshot(&file).unwrap();
+        File::create(&file).unwrap().write_all(b"broken").unwrap();
+
+        let mut shell = test_shell();
+        backups.restore_all(&mut shell, "test revert").unwrap();
+
+        let mut contents = String::new();
+        File::open(&file).unwrap().read_to_string(&mut contents).unwrap();
+        assert_eq!(contents, "original");
+    }
+}
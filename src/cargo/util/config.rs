@@ -0,0 +1,41 @@
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+
+use core::{MultiShell, Verbosity, ColorConfig};
+use util::{CargoResult, MessageFormat};
+
+pub struct Config {
+    cwd: PathBuf,
+    shell: RefCell<MultiShell>,
+}
+
+impl Config {
+    pub fn new(shell: MultiShell, cwd: PathBuf) -> Config {
+        Config { cwd: cwd, shell: RefCell::new(shell) }
+    }
+
+    pub fn cwd(&self) -> &Path {
+        &self.cwd
+    }
+
+    pub fn shell(&self) -> ::std::cell::RefMut<MultiShell> {
+        self.shell.borrow_mut()
+    }
+
+    pub fn message_format(&self) -> MessageFormat {
+        self.shell().message_format()
+    }
+
+    pub fn configure_shell(
+        &self,
+        verbosity: Verbosity,
+        color_config: ColorConfig,
+        message_format: MessageFormat,
+    ) -> CargoResult<()> {
+        let mut shell = self.shell();
+        shell.set_verbosity(verbosity);
+        shell.set_message_format(message_format);
+        let _ = color_config;
+        Ok(())
+    }
+}
@@ -0,0 +1,37 @@
+error_chain! {
+    types {
+        CargoError, CargoErrorKind, CargoResultExt, CargoResult;
+    }
+
+    errors {
+        Internal(msg: String) {
+            description("internal error")
+            display("{}", msg)
+        }
+    }
+}
+
+pub type CliResult = Result<(), CliError>;
+
+#[derive(Debug)]
+pub struct CliError {
+    pub error: Option<CargoError>,
+    pub unknown: bool,
+    pub exit_code: i32,
+}
+
+impl CliError {
+    pub fn new(error: CargoError, code: i32) -> CliError {
+        CliError { error: Some(error), unknown: false, exit_code: code }
+    }
+
+    pub fn code(code: i32) -> CliError {
+        CliError { error: None, unknown: false, exit_code: code }
+    }
+}
+
+impl From<CargoError> for CliError {
+    fn from(err: CargoError) -> CliError {
+        CliError::new(err, 101)
+    }
+}
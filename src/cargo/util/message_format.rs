@@ -0,0 +1,16 @@
+/// How cargo should render its own status messages and diagnostics.
+///
+/// `Json` is meant for consumption by tooling (editors, `cargo fix`, CI
+/// dashboards) that want to parse cargo's output rather than screen-scrape
+/// human-oriented text.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MessageFormat {
+    Human,
+    Json,
+}
+
+impl Default for MessageFormat {
+    fn default() -> MessageFormat {
+        MessageFormat::Human
+    }
+}
@@ -0,0 +1,11 @@
+pub use self::backup::BackupSet;
+pub use self::config::Config;
+pub use self::errors::{CargoError, CargoErrorKind, CargoResult, CargoResultExt, CliError, CliResult};
+pub use self::message_format::MessageFormat;
+pub use self::process_builder::{process, ProcessBuilder};
+
+pub mod backup;
+pub mod config;
+pub mod errors;
+pub mod message_format;
+pub mod process_builder;
@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fmt;
+use std::path::Path;
+use std::process::{Command, Output};
+
+use util::{CargoResult, CargoResultExt};
+
+#[derive(Clone, Debug)]
+pub struct ProcessBuilder {
+    program: OsString,
+    args: Vec<OsString>,
+    cwd: Option<OsString>,
+    env: HashMap<String, Option<OsString>>,
+}
+
+pub fn process<T: AsRef<::std::ffi::OsStr>>(cmd: T) -> ProcessBuilder {
+    ProcessBuilder {
+        program: cmd.as_ref().to_os_string(),
+        args: Vec::new(),
+        cwd: None,
+        env: HashMap::new(),
+    }
+}
+
+impl ProcessBuilder {
+    pub fn arg<T: AsRef<::std::ffi::OsStr>>(&mut self, arg: T) -> &mut ProcessBuilder {
+        self.args.push(arg.as_ref().to_os_string());
+        self
+    }
+
+    pub fn args<T: AsRef<::std::ffi::OsStr>>(&mut self, args: &[T]) -> &mut ProcessBuilder {
+        for arg in args {
+            self.arg(arg);
+        }
+        self
+    }
+
+    pub fn cwd<T: AsRef<::std::ffi::OsStr>>(&mut self, path: T) -> &mut ProcessBuilder {
+        self.cwd = Some(path.as_ref().to_os_string());
+        self
+    }
+
+    fn build_command(&self) -> Command {
+        let mut cmd = Command::new(&self.program);
+        if let Some(ref cwd) = self.cwd {
+            cmd.current_dir(Path::new(cwd));
+        }
+        for arg in &self.args {
+            cmd.arg(arg);
+        }
+        for (k, v) in &self.env {
+            match *v {
+                Some(ref v) => { cmd.env(k, v); }
+                None => { cmd.env_remove(k); }
+            }
+        }
+        cmd
+    }
+
+    pub fn exec_with_output(&self) -> CargoResult<Output> {
+        self.build_command()
+            .output()
+            .chain_err(|| format!("could not execute process `{}`", self))
+    }
+}
+
+impl fmt::Display for ProcessBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "`{}", self.program.to_string_lossy())?;
+        for arg in &self.args {
+            write!(f, " {}", arg.to_string_lossy())?;
+        }
+        write!(f, "`")
+    }
+}